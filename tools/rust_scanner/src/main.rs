@@ -1,14 +1,47 @@
+use std::collections::{HashSet, VecDeque};
 use std::env;
 use std::fs;
-use std::io::{self, Write};
+use std::io::{self, BufRead, Write};
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
 
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 struct Opts {
     root: PathBuf,
     max_depth: usize,
     follow_symlinks: bool,
-    exclude_dirs: Vec<String>,
+    exclude_dirs: Arc<GlobSet>,
+    ignore_file: Option<PathBuf>,
+    no_ignore: bool,
+    threads: usize,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    newer_than: Option<i64>,
+    older_than: Option<i64>,
+    format: Format,
+    types: EntryTypes,
+    extensions: Vec<String>,
+}
+
+// Defaults to files only, matching the tool's original hard-coded behavior.
+#[derive(Debug, Clone, Copy)]
+struct EntryTypes {
+    files: bool,
+    dirs: bool,
+    symlinks: bool,
+    executables: bool,
+}
+
+impl Default for EntryTypes {
+    fn default() -> EntryTypes {
+        EntryTypes {
+            files: true,
+            dirs: false,
+            symlinks: false,
+            executables: false,
+        }
+    }
 }
 
 fn parse_args() -> Result<Opts, String> {
@@ -17,6 +50,17 @@ fn parse_args() -> Result<Opts, String> {
     let mut max_depth: usize = 64;
     let mut follow_symlinks = false;
     let mut exclude_dirs: Vec<String> = Vec::new();
+    let mut ignore_file: Option<PathBuf> = None;
+    let mut no_ignore = false;
+    let mut threads: usize = 1;
+    let mut min_size: Option<u64> = None;
+    let mut max_size: Option<u64> = None;
+    let mut newer_than: Option<i64> = None;
+    let mut older_than: Option<i64> = None;
+    let mut format = Format::Tsv;
+    let mut types = EntryTypes::default();
+    let mut types_explicit = false;
+    let mut extensions: Vec<String> = Vec::new();
 
     while let Some(arg) = args.next() {
         match arg.as_str() {
@@ -37,6 +81,71 @@ fn parse_args() -> Result<Opts, String> {
                     exclude_dirs.push(v.trim().to_string());
                 }
             }
+            "--ignore-file" => {
+                let v = args.next().ok_or("missing value for --ignore-file")?;
+                ignore_file = Some(PathBuf::from(v));
+            }
+            "--no-ignore" => {
+                no_ignore = true;
+            }
+            "--threads" => {
+                let v = args.next().ok_or("missing value for --threads")?;
+                threads = v.parse::<usize>().map_err(|_| "invalid thread count")?;
+                if threads == 0 {
+                    return Err("--threads must be at least 1".to_string());
+                }
+            }
+            "--min-size" => {
+                let v = args.next().ok_or("missing value for --min-size")?;
+                min_size = Some(parse_size(&v)?);
+            }
+            "--max-size" => {
+                let v = args.next().ok_or("missing value for --max-size")?;
+                max_size = Some(parse_size(&v)?);
+            }
+            "--newer-than" => {
+                let v = args.next().ok_or("missing value for --newer-than")?;
+                newer_than = Some(parse_time_spec(&v)?);
+            }
+            "--older-than" => {
+                let v = args.next().ok_or("missing value for --older-than")?;
+                older_than = Some(parse_time_spec(&v)?);
+            }
+            "--format" => {
+                let v = args.next().ok_or("missing value for --format")?;
+                format = match v.as_str() {
+                    "tsv" => Format::Tsv,
+                    "jsonl" => Format::Jsonl,
+                    "bin" => Format::Bin,
+                    other => return Err(format!("unknown format: {}", other)),
+                };
+            }
+            "--type" => {
+                let v = args.next().ok_or("missing value for --type")?;
+                if !types_explicit {
+                    types = EntryTypes {
+                        files: false,
+                        dirs: false,
+                        symlinks: false,
+                        executables: false,
+                    };
+                    types_explicit = true;
+                }
+                match v.as_str() {
+                    "f" => types.files = true,
+                    "d" => types.dirs = true,
+                    "l" => types.symlinks = true,
+                    "x" => types.executables = true,
+                    other => return Err(format!("unknown --type: {}", other)),
+                }
+            }
+            "--extension" => {
+                let v = args.next().ok_or("missing value for --extension")?;
+                let ext = v.trim().trim_start_matches('.').to_string();
+                if !ext.is_empty() {
+                    extensions.push(ext);
+                }
+            }
             _ => return Err(format!("unknown argument: {}", arg)),
         }
     }
@@ -46,10 +155,212 @@ fn parse_args() -> Result<Opts, String> {
         root,
         max_depth,
         follow_symlinks,
-        exclude_dirs,
+        exclude_dirs: Arc::new(GlobSet::build(&exclude_dirs)),
+        ignore_file,
+        no_ignore,
+        threads,
+        min_size,
+        max_size,
+        newer_than,
+        older_than,
+        format,
+        types,
+        extensions,
     })
 }
 
+fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err("empty size".to_string());
+    }
+    let (digits, mult) = match s.chars().last().unwrap().to_ascii_lowercase() {
+        'k' => (&s[..s.len() - 1], 1024u64),
+        'm' => (&s[..s.len() - 1], 1024 * 1024),
+        'g' => (&s[..s.len() - 1], 1024 * 1024 * 1024),
+        _ => (s, 1),
+    };
+    let value: u64 = digits.trim().parse().map_err(|_| format!("invalid size: {}", s))?;
+    value
+        .checked_mul(mult)
+        .ok_or_else(|| format!("size overflow: {}", s))
+}
+
+// A relative duration (`7d`, `2h`, ...) resolves against the current time.
+fn parse_time_spec(s: &str) -> Result<i64, String> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err("empty timestamp".to_string());
+    }
+    if let Some(secs) = parse_relative_duration(s) {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(|_| "system clock before epoch".to_string())?
+            .as_secs() as i64;
+        return Ok(now - secs);
+    }
+    parse_rfc3339(s).ok_or_else(|| format!("invalid timestamp: {}", s))
+}
+
+fn parse_relative_duration(s: &str) -> Option<i64> {
+    let last = s.chars().last()?;
+    let unit = match last.to_ascii_lowercase() {
+        's' => 1i64,
+        'm' => 60,
+        'h' => 3600,
+        'd' => 86400,
+        _ => return None,
+    };
+    let digits = &s[..s.len() - 1];
+    let value: i64 = digits.parse().ok()?;
+    Some(value * unit)
+}
+
+fn parse_rfc3339(s: &str) -> Option<i64> {
+    let bytes = s.as_bytes();
+    if bytes.len() < 19 {
+        return None;
+    }
+    let year: i64 = s.get(0..4)?.parse().ok()?;
+    if s.as_bytes().get(4) != Some(&b'-') {
+        return None;
+    }
+    let month: i64 = s.get(5..7)?.parse().ok()?;
+    if s.as_bytes().get(7) != Some(&b'-') {
+        return None;
+    }
+    let day: i64 = s.get(8..10)?.parse().ok()?;
+    let sep = s.as_bytes().get(10)?;
+    if *sep != b'T' && *sep != b't' && *sep != b' ' {
+        return None;
+    }
+    let hour: i64 = s.get(11..13)?.parse().ok()?;
+    if s.as_bytes().get(13) != Some(&b':') {
+        return None;
+    }
+    let minute: i64 = s.get(14..16)?.parse().ok()?;
+    if s.as_bytes().get(16) != Some(&b':') {
+        return None;
+    }
+    let second: i64 = s.get(17..19)?.parse().ok()?;
+
+    let mut offset_secs = 0i64;
+    if let Some(rest) = s.get(19..) {
+        let rest = rest.trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+        if !rest.is_empty() && rest != "Z" && rest != "z" {
+            let sign = match rest.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let oh: i64 = rest.get(1..3)?.parse().ok()?;
+            let om: i64 = rest.get(4..6)?.parse().ok()?;
+            offset_secs = sign * (oh * 3600 + om * 60);
+        }
+    }
+
+    let days = days_since_epoch(year, month, day)?;
+    Some(days * 86400 + hour * 3600 + minute * 60 + second - offset_secs)
+}
+
+fn is_leap_year(y: i64) -> bool {
+    (y % 4 == 0 && y % 100 != 0) || y % 400 == 0
+}
+
+fn days_since_epoch(year: i64, month: i64, day: i64) -> Option<i64> {
+    if !(1..=12).contains(&month) {
+        return None;
+    }
+    let month_days = [31, 28, 31, 30, 31, 30, 31, 31, 30, 31, 30, 31];
+    let days_in_month = month_days[(month - 1) as usize]
+        + if month == 2 && is_leap_year(year) { 1 } else { 0 };
+    if !(1..=days_in_month).contains(&day) {
+        return None;
+    }
+    let mut days: i64 = 0;
+    if year >= 1970 {
+        for y in 1970..year {
+            days += if is_leap_year(y) { 366 } else { 365 };
+        }
+    } else {
+        for y in year..1970 {
+            days -= if is_leap_year(y) { 366 } else { 365 };
+        }
+    }
+    for (m, &md) in month_days.iter().enumerate().take((month - 1) as usize) {
+        days += md;
+        if m == 1 && is_leap_year(year) {
+            days += 1;
+        }
+    }
+    days += day - 1;
+    Some(days)
+}
+
+// Uses metadata the caller already read, so no extra syscalls per file.
+fn passes_filters(opts: &Opts, mtime: i64, size: u64) -> bool {
+    if let Some(min) = opts.min_size {
+        if size < min {
+            return false;
+        }
+    }
+    if let Some(max) = opts.max_size {
+        if size > max {
+            return false;
+        }
+    }
+    if let Some(newer_than) = opts.newer_than {
+        if mtime < newer_than {
+            return false;
+        }
+    }
+    if let Some(older_than) = opts.older_than {
+        if mtime > older_than {
+            return false;
+        }
+    }
+    true
+}
+
+fn mtime_of(meta: &fs::Metadata) -> i64 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(unix)]
+fn is_executable(meta: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    meta.permissions().mode() & 0o111 != 0
+}
+
+#[cfg(not(unix))]
+fn is_executable(_meta: &fs::Metadata) -> bool {
+    false
+}
+
+fn extension_matches(path: &Path, extensions: &[String]) -> bool {
+    if extensions.is_empty() {
+        return true;
+    }
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => extensions.iter().any(|wanted| wanted == ext),
+        None => false,
+    }
+}
+
+fn should_emit(opts: &Opts, ft: &fs::FileType, is_exec: bool, path: &Path) -> bool {
+    if !extension_matches(path, &opts.extensions) {
+        return false;
+    }
+    (opts.types.files && ft.is_file())
+        || (opts.types.dirs && ft.is_dir())
+        || (opts.types.symlinks && ft.is_symlink())
+        || (opts.types.executables && ft.is_file() && is_exec)
+}
+
 fn normalize_pattern_token(pat: &str) -> String {
     let mut s = pat.trim().replace('\\', "/");
     if s.ends_with("/**") {
@@ -61,35 +372,836 @@ fn normalize_pattern_token(pat: &str) -> String {
     s
 }
 
-fn should_exclude_dir(dir_name: &str, rel_posix: &str, patterns: &[String]) -> bool {
-    for pat in patterns {
-        let token = normalize_pattern_token(pat);
-        if token.is_empty() {
-            continue;
+#[derive(Clone, Copy)]
+enum LiteralAnchor {
+    Suffix,
+    Prefix,
+}
+
+struct LiteralPattern {
+    text: String,
+    anchor: LiteralAnchor,
+}
+
+// Scans a candidate string against many literal suffix/prefix fragments in a
+// single pass, instead of calling ends_with/starts_with once per pattern.
+struct AhoCorasick {
+    goto_: Vec<[i32; 256]>,
+    fail: Vec<usize>,
+    // Pattern indices whose literal text ends at this node.
+    output: Vec<Vec<usize>>,
+    patterns: Vec<LiteralPattern>,
+}
+
+impl AhoCorasick {
+    fn build(patterns: Vec<LiteralPattern>) -> AhoCorasick {
+        let mut goto_ = vec![[-1i32; 256]];
+        let mut output: Vec<Vec<usize>> = vec![Vec::new()];
+
+        for (idx, pat) in patterns.iter().enumerate() {
+            let mut node = 0usize;
+            for &b in pat.text.as_bytes() {
+                let next = goto_[node][b as usize];
+                node = if next >= 0 {
+                    next as usize
+                } else {
+                    goto_.push([-1i32; 256]);
+                    output.push(Vec::new());
+                    let new_node = goto_.len() - 1;
+                    goto_[node][b as usize] = new_node as i32;
+                    new_node
+                };
+            }
+            output[node].push(idx);
+        }
+
+        let mut fail = vec![0usize; goto_.len()];
+        let mut queue = std::collections::VecDeque::new();
+        for &nxt in goto_[0].iter() {
+            if nxt >= 0 {
+                fail[nxt as usize] = 0;
+                queue.push_back(nxt as usize);
+            }
+        }
+        while let Some(node) = queue.pop_front() {
+            for (b, &nxt) in goto_[node].iter().enumerate() {
+                if nxt < 0 {
+                    continue;
+                }
+                let nxt = nxt as usize;
+                let mut f = fail[node];
+                fail[nxt] = loop {
+                    let via = goto_[f][b];
+                    if via >= 0 {
+                        break via as usize;
+                    }
+                    if f == 0 {
+                        break 0;
+                    }
+                    f = fail[f];
+                };
+                let inherited = output[fail[nxt]].clone();
+                output[nxt].extend(inherited);
+                queue.push_back(nxt);
+            }
+        }
+
+        AhoCorasick {
+            goto_,
+            fail,
+            output,
+            patterns,
+        }
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        if self.patterns.is_empty() {
+            return false;
+        }
+        let bytes = text.as_bytes();
+        let mut node = 0usize;
+        for (i, &b) in bytes.iter().enumerate() {
+            loop {
+                let nxt = self.goto_[node][b as usize];
+                if nxt >= 0 {
+                    node = nxt as usize;
+                    break;
+                }
+                if node == 0 {
+                    break;
+                }
+                node = self.fail[node];
+            }
+            for &pat_idx in &self.output[node] {
+                let pat = &self.patterns[pat_idx];
+                let end = i + 1;
+                let start = end - pat.text.len();
+                let positioned = match pat.anchor {
+                    LiteralAnchor::Suffix => end == bytes.len(),
+                    LiteralAnchor::Prefix => start == 0,
+                };
+                if positioned {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+// `--exclude-dir` patterns, bucketed by shape (exact token, literal
+// suffix/prefix glob, basename glob, path glob) so matching stays cheap
+// even with hundreds of patterns.
+struct GlobSet {
+    exact_names: HashSet<String>,
+    exact_subtrees: Vec<String>,
+    literals: AhoCorasick,
+    basename_globs: Vec<Vec<String>>,
+    path_globs: Vec<Vec<String>>,
+}
+
+impl GlobSet {
+    fn build(patterns: &[String]) -> GlobSet {
+        let mut exact_names = HashSet::new();
+        let mut exact_subtrees = Vec::new();
+        let mut literals = Vec::new();
+        let mut basename_globs = Vec::new();
+        let mut path_globs = Vec::new();
+
+        for pat in patterns {
+            let token = normalize_pattern_token(pat);
+            if token.is_empty() {
+                continue;
+            }
+            let has_meta = token.contains('*') || token.contains('?') || token.contains('[');
+            if !has_meta {
+                exact_names.insert(token.clone());
+                exact_subtrees.push(token);
+                continue;
+            }
+
+            // `*literal` (pure suffix) and `literal*` (pure prefix) compile
+            // to one Aho-Corasick literal each; anything with more than one
+            // wildcard or a `/` falls through to the segment matchers.
+            let stars = token.matches('*').count();
+            let has_other_meta = token.contains('?') || token.contains('[');
+            if !token.contains('/') && stars == 1 && !has_other_meta {
+                if let Some(suffix) = token.strip_prefix('*') {
+                    if !suffix.is_empty() {
+                        literals.push(LiteralPattern {
+                            text: suffix.to_string(),
+                            anchor: LiteralAnchor::Suffix,
+                        });
+                        continue;
+                    }
+                }
+                if let Some(prefix) = token.strip_suffix('*') {
+                    if !prefix.is_empty() {
+                        literals.push(LiteralPattern {
+                            text: prefix.to_string(),
+                            anchor: LiteralAnchor::Prefix,
+                        });
+                        continue;
+                    }
+                }
+            }
+
+            let segments: Vec<String> = token.split('/').map(|s| s.to_string()).collect();
+            if segments.len() == 1 {
+                basename_globs.push(segments);
+            } else {
+                path_globs.push(segments);
+            }
+        }
+
+        GlobSet {
+            exact_names,
+            exact_subtrees,
+            literals: AhoCorasick::build(literals),
+            basename_globs,
+            path_globs,
+        }
+    }
+
+    fn is_match(&self, dir_name: &str, rel_posix: &str) -> bool {
+        if self.exact_names.contains(dir_name) || self.exact_names.contains(rel_posix) {
+            return true;
+        }
+        if self
+            .exact_subtrees
+            .iter()
+            .any(|tok| rel_posix.starts_with(tok.as_str()) && rel_posix[tok.len()..].starts_with('/'))
+        {
+            return true;
+        }
+        if self.literals.is_match(dir_name) {
+            return true;
+        }
+        if self
+            .basename_globs
+            .iter()
+            .any(|seg| glob_segment_match(&seg[0], dir_name))
+        {
+            return true;
+        }
+        let rel_segments: Vec<&str> = rel_posix.split('/').collect();
+        self.path_globs
+            .iter()
+            .any(|pat| glob_match_segments(pat, &rel_segments))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct IgnoreRule {
+    negate: bool,
+    dir_only: bool,
+    anchored: bool,
+    // Pattern split on '/', with "**" kept as a literal segment marker.
+    segments: Vec<String>,
+}
+
+impl IgnoreRule {
+    fn parse(line: &str) -> Option<IgnoreRule> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let mut s = line.to_string();
+        let negate = if s.starts_with('!') {
+            s.remove(0);
+            true
+        } else {
+            false
+        };
+        // A leading backslash escapes '!' or '#'; strip it.
+        if s.starts_with("\\!") || s.starts_with("\\#") {
+            s.remove(0);
+        }
+        let dir_only = s.ends_with('/') && !s.ends_with("\\/");
+        if dir_only {
+            s.pop();
+        }
+        if s.is_empty() {
+            return None;
         }
-        if !token.contains('*') && !token.contains('?') && !token.contains('[') {
-            if dir_name == token || rel_posix == token || rel_posix.starts_with(&(token.clone() + "/")) {
+        let anchored = s.starts_with('/');
+        let s = s.trim_start_matches('/');
+        let segments = s.split('/').map(|seg| seg.to_string()).collect();
+        Some(IgnoreRule {
+            negate,
+            dir_only,
+            anchored,
+            segments,
+        })
+    }
+
+    fn matches(&self, rel_segments: &[&str], is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        if self.anchored || self.segments.len() > 1 {
+            glob_match_segments(&self.segments, rel_segments)
+        } else {
+            // Unanchored single-segment pattern matches at any depth.
+            rel_segments
+                .iter()
+                .enumerate()
+                .any(|(i, _)| glob_match_segments(&self.segments, &rel_segments[i..]))
+        }
+    }
+}
+
+// Bottom-up dp[i][j] ("pattern[i..] matches path[j..]") instead of recursive
+// backtracking on `**`, which is exponential in the number of `**` segments.
+fn glob_match_segments(pattern: &[String], path: &[&str]) -> bool {
+    let plen = pattern.len();
+    let slen = path.len();
+    let mut dp = vec![vec![false; slen + 1]; plen + 1];
+    dp[plen][slen] = true;
+    for i in (0..plen).rev() {
+        if pattern[i] == "**" {
+            dp[i][slen] = dp[i + 1][slen];
+        }
+    }
+    for j in (0..slen).rev() {
+        for i in (0..plen).rev() {
+            dp[i][j] = if pattern[i] == "**" {
+                dp[i + 1][j] || dp[i][j + 1]
+            } else {
+                glob_segment_match(&pattern[i], path[j]) && dp[i + 1][j + 1]
+            };
+        }
+    }
+    dp[0][0]
+}
+
+enum GlobToken {
+    Literal(char),
+    Any,
+    Class { negate: bool, chars: Vec<char> },
+    Star,
+}
+
+fn tokenize_glob_segment(pattern: &[char]) -> Vec<GlobToken> {
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < pattern.len() {
+        match pattern[i] {
+            '*' => {
+                tokens.push(GlobToken::Star);
+                i += 1;
+            }
+            '?' => {
+                tokens.push(GlobToken::Any);
+                i += 1;
+            }
+            '[' => match pattern[i..].iter().position(|&c| c == ']').filter(|&idx| idx > 0) {
+                Some(rel_close) => {
+                    let close = i + rel_close;
+                    let (negate, start) = if pattern[i + 1] == '!' {
+                        (true, i + 2)
+                    } else {
+                        (false, i + 1)
+                    };
+                    tokens.push(GlobToken::Class {
+                        negate,
+                        chars: pattern[start..close].to_vec(),
+                    });
+                    i = close + 1;
+                }
+                None => {
+                    tokens.push(GlobToken::Literal('['));
+                    i += 1;
+                }
+            },
+            c => {
+                tokens.push(GlobToken::Literal(c));
+                i += 1;
+            }
+        }
+    }
+    tokens
+}
+
+fn glob_token_matches(token: &GlobToken, c: char) -> bool {
+    match token {
+        GlobToken::Literal(l) => *l == c,
+        GlobToken::Any => true,
+        GlobToken::Class { negate, chars } => char_in_class(chars, c) != *negate,
+        GlobToken::Star => unreachable!("Star is handled structurally, not per character"),
+    }
+}
+
+// Tokenizes once, then fills a dp[token][char] table left to right instead
+// of backtracking: recursing on both branches of every `*` is exponential
+// on inputs like `*a*a*a*...*ax`, reachable here from .gitignore content.
+fn glob_segment_match(pattern: &str, text: &str) -> bool {
+    let pattern_chars: Vec<char> = pattern.chars().collect();
+    let tokens = tokenize_glob_segment(&pattern_chars);
+    let t: Vec<char> = text.chars().collect();
+
+    let mut prev = vec![false; t.len() + 1];
+    prev[0] = true;
+
+    for token in &tokens {
+        let mut cur = vec![false; t.len() + 1];
+        match token {
+            GlobToken::Star => {
+                cur[0] = prev[0];
+                for j in 1..=t.len() {
+                    cur[j] = cur[j - 1] || prev[j];
+                }
+            }
+            _ => {
+                for j in 1..=t.len() {
+                    cur[j] = prev[j - 1] && glob_token_matches(token, t[j - 1]);
+                }
+            }
+        }
+        prev = cur;
+    }
+    prev[t.len()]
+}
+
+fn char_in_class(class: &[char], c: char) -> bool {
+    let mut i = 0;
+    while i < class.len() {
+        if i + 2 < class.len() && class[i + 1] == '-' {
+            if c >= class[i] && c <= class[i + 2] {
                 return true;
             }
+            i += 3;
+        } else {
+            if class[i] == c {
+                return true;
+            }
+            i += 1;
         }
     }
     false
 }
 
+struct IgnoreFrame {
+    base: PathBuf,
+    rules: Vec<IgnoreRule>,
+}
+
+fn load_ignore_rules(path: &Path) -> Vec<IgnoreRule> {
+    let file = match fs::File::open(path) {
+        Ok(f) => f,
+        Err(_) => return Vec::new(),
+    };
+    io::BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|l| IgnoreRule::parse(&l))
+        .collect()
+}
+
+fn gather_ignore_frames(dir: &Path, extra_ignore_file: Option<&Path>) -> Vec<IgnoreFrame> {
+    let mut frames = Vec::new();
+    let gitignore = dir.join(".gitignore");
+    if gitignore.is_file() {
+        frames.push(IgnoreFrame {
+            base: dir.to_path_buf(),
+            rules: load_ignore_rules(&gitignore),
+        });
+    }
+    if let Some(extra) = extra_ignore_file {
+        if extra.is_file() {
+            frames.push(IgnoreFrame {
+                base: dir.to_path_buf(),
+                rules: load_ignore_rules(extra),
+            });
+        }
+    }
+    frames
+}
+
+// Frames run outermost-first so a deeper rule can override a shallower one;
+// the last match in the sequence wins, matching gitignore semantics.
+fn is_ignored_by_frames<'a, I: Iterator<Item = &'a IgnoreFrame>>(
+    frames: I,
+    path: &Path,
+    is_dir: bool,
+) -> bool {
+    let mut ignored = false;
+    for frame in frames {
+        let rel = match path.strip_prefix(&frame.base) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        let rel_posix = rel.to_string_lossy().replace('\\', "/");
+        if rel_posix.is_empty() {
+            continue;
+        }
+        let rel_segments: Vec<&str> = rel_posix.split('/').collect();
+        for rule in &frame.rules {
+            if rule.matches(&rel_segments, is_dir) {
+                ignored = !rule.negate;
+            }
+        }
+    }
+    ignored
+}
+
+struct IgnoreStack {
+    frames: Vec<IgnoreFrame>,
+}
+
+impl IgnoreStack {
+    fn new() -> IgnoreStack {
+        IgnoreStack { frames: Vec::new() }
+    }
+
+    // Returns how many frames were pushed, so the caller pops the same count.
+    fn push_dir(&mut self, dir: &Path, extra_ignore_file: Option<&Path>) -> usize {
+        let new_frames = gather_ignore_frames(dir, extra_ignore_file);
+        let pushed = new_frames.len();
+        self.frames.extend(new_frames);
+        pushed
+    }
+
+    fn pop(&mut self, count: usize) {
+        for _ in 0..count {
+            self.frames.pop();
+        }
+    }
+
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        is_ignored_by_frames(self.frames.iter(), path, is_dir)
+    }
+}
+
+// Immutable equivalent of IgnoreStack: work items cross threads out of
+// call-stack order, so each carries its own inherited chain (cheaply
+// Clone'd via Arc) instead of sharing one push/pop stack.
+#[derive(Clone)]
+struct IgnoreChain(Option<Arc<IgnoreChainNode>>);
+
+struct IgnoreChainNode {
+    frame: IgnoreFrame,
+    parent: IgnoreChain,
+}
+
+impl IgnoreChain {
+    fn new() -> IgnoreChain {
+        IgnoreChain(None)
+    }
+
+    fn extended(&self, dir: &Path, extra_ignore_file: Option<&Path>) -> IgnoreChain {
+        let mut chain = self.clone();
+        for frame in gather_ignore_frames(dir, extra_ignore_file) {
+            chain = IgnoreChain(Some(Arc::new(IgnoreChainNode {
+                frame,
+                parent: chain,
+            })));
+        }
+        chain
+    }
+
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let mut frames = Vec::new();
+        let mut cur = self;
+        while let Some(node) = &cur.0 {
+            frames.push(&node.frame);
+            cur = &node.parent;
+        }
+        frames.reverse();
+        is_ignored_by_frames(frames.into_iter(), path, is_dir)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Tsv,
+    Jsonl,
+    Bin,
+}
+
+trait RecordWriter: Send {
+    fn write_record(&mut self, path: &Path, mtime: i64, size: u64) -> io::Result<()>;
+}
+
+struct TsvWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write + Send> RecordWriter for TsvWriter<W> {
+    fn write_record(&mut self, path: &Path, mtime: i64, size: u64) -> io::Result<()> {
+        writeln!(self.out, "{}\t{}\t{}", path.display(), mtime, size)
+    }
+}
+
+struct JsonlWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write + Send> RecordWriter for JsonlWriter<W> {
+    fn write_record(&mut self, path: &Path, mtime: i64, size: u64) -> io::Result<()> {
+        writeln!(
+            self.out,
+            "{{\"path\":{},\"mtime\":{},\"size\":{}}}",
+            json_escape_path(path),
+            mtime,
+            size
+        )
+    }
+}
+
+// Lossy like the rest of the tool: non-UTF-8 bytes become the replacement
+// character instead of failing the scan.
+fn json_escape_path(path: &Path) -> String {
+    let mut s = String::from("\"");
+    for c in path.to_string_lossy().chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            '\t' => s.push_str("\\t"),
+            c if (c as u32) < 0x20 => s.push_str(&format!("\\u{:04x}", c as u32)),
+            c => s.push(c),
+        }
+    }
+    s.push('"');
+    s
+}
+
+const BIN_MAGIC: &[u8; 4] = b"SARI";
+const BIN_VERSION: u8 = 1;
+
+// 5-byte header (magic + version), then per record: varint path length,
+// path bytes, little-endian i64 mtime, little-endian u64 size.
+struct BinWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> BinWriter<W> {
+    fn new(mut out: W) -> io::Result<BinWriter<W>> {
+        out.write_all(BIN_MAGIC)?;
+        out.write_all(&[BIN_VERSION])?;
+        Ok(BinWriter { out })
+    }
+}
+
+impl<W: Write + Send> RecordWriter for BinWriter<W> {
+    fn write_record(&mut self, path: &Path, mtime: i64, size: u64) -> io::Result<()> {
+        let path_bytes = path.to_string_lossy();
+        let path_bytes = path_bytes.as_bytes();
+        write_varint(&mut self.out, path_bytes.len() as u64)?;
+        self.out.write_all(path_bytes)?;
+        self.out.write_all(&mtime.to_le_bytes())?;
+        self.out.write_all(&size.to_le_bytes())?;
+        Ok(())
+    }
+}
+
+fn write_varint<W: Write>(out: &mut W, mut value: u64) -> io::Result<()> {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.write_all(&[byte])?;
+            return Ok(());
+        }
+        out.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn build_writer<W: Write + Send + 'static>(
+    format: Format,
+    out: W,
+) -> io::Result<Box<dyn RecordWriter + Send>> {
+    Ok(match format {
+        Format::Tsv => Box::new(TsvWriter { out }),
+        Format::Jsonl => Box::new(JsonlWriter { out }),
+        Format::Bin => Box::new(BinWriter::new(out)?),
+    })
+}
+
 fn scan_dir(
     root: &Path,
     current: &Path,
     depth: usize,
     opts: &Opts,
-    out: &mut dyn Write,
+    ignore: &mut IgnoreStack,
+    out: &mut (dyn RecordWriter + Send),
 ) -> io::Result<()> {
     if depth > opts.max_depth {
         return Ok(());
     }
 
+    let extra_ignore_file = if depth == 0 {
+        opts.ignore_file.as_deref()
+    } else {
+        None
+    };
+    let pushed = if opts.no_ignore {
+        0
+    } else {
+        ignore.push_dir(current, extra_ignore_file)
+    };
+
     let entries = match fs::read_dir(current) {
         Ok(v) => v,
-        Err(_) => return Ok(()),
+        Err(_) => {
+            ignore.pop(pushed);
+            return Ok(());
+        }
+    };
+
+    for entry_res in entries {
+        let entry = match entry_res {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        let path = entry.path();
+
+        let ft = match entry.file_type() {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+
+        let rel = match path.strip_prefix(root) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+        let rel_posix = rel.to_string_lossy().replace('\\', "/");
+
+        if ft.is_dir() {
+            let dir_name = entry.file_name().to_string_lossy().to_string();
+            if opts.exclude_dirs.is_match(&dir_name, &rel_posix) {
+                continue;
+            }
+            if !opts.no_ignore && ignore.is_ignored(&path, true) {
+                continue;
+            }
+            if opts.types.dirs && should_emit(opts, &ft, false, &path) {
+                if let Ok(meta) = fs::metadata(&path) {
+                    let mtime = mtime_of(&meta);
+                    let size = meta.len();
+                    if passes_filters(opts, mtime, size) {
+                        out.write_record(&path, mtime, size)?;
+                    }
+                }
+            }
+            if !opts.follow_symlinks && ft.is_symlink() {
+                continue;
+            }
+            let _ = scan_dir(root, &path, depth + 1, opts, ignore, out);
+            continue;
+        }
+
+        if ft.is_file() {
+            if !opts.no_ignore && ignore.is_ignored(&path, false) {
+                continue;
+            }
+            let meta = match fs::metadata(&path) {
+                Ok(m) => m,
+                Err(_) => continue,
+            };
+            let mtime = mtime_of(&meta);
+            let size = meta.len();
+            if !passes_filters(opts, mtime, size) {
+                continue;
+            }
+            if should_emit(opts, &ft, is_executable(&meta), &path) {
+                // Keep raw entry path to avoid expensive per-file canonicalize.
+                out.write_record(&path, mtime, size)?;
+            }
+            continue;
+        }
+
+        if ft.is_symlink() {
+            if !opts.no_ignore && ignore.is_ignored(&path, false) {
+                continue;
+            }
+            if opts.types.symlinks && should_emit(opts, &ft, false, &path) {
+                if let Ok(meta) = fs::symlink_metadata(&path) {
+                    let mtime = mtime_of(&meta);
+                    let size = meta.len();
+                    if passes_filters(opts, mtime, size) {
+                        out.write_record(&path, mtime, size)?;
+                    }
+                }
+            }
+        }
+    }
+    ignore.pop(pushed);
+    Ok(())
+}
+
+struct WorkItem {
+    dir: PathBuf,
+    depth: usize,
+    ignore: IgnoreChain,
+}
+
+// `outstanding` counts items in the queue plus ones being processed right
+// now; it hits zero exactly when there's no work left anywhere, which is
+// the walker's termination condition.
+struct WorkQueue {
+    items: Mutex<VecDeque<WorkItem>>,
+    cond: Condvar,
+    outstanding: AtomicUsize,
+}
+
+impl WorkQueue {
+    fn push(&self, item: WorkItem) {
+        self.outstanding.fetch_add(1, Ordering::SeqCst);
+        self.items.lock().unwrap().push_back(item);
+        self.cond.notify_one();
+    }
+
+    fn pop(&self) -> Option<WorkItem> {
+        let mut items = self.items.lock().unwrap();
+        loop {
+            if let Some(item) = items.pop_front() {
+                return Some(item);
+            }
+            if self.outstanding.load(Ordering::SeqCst) == 0 {
+                self.cond.notify_all();
+                return None;
+            }
+            items = self.cond.wait(items).unwrap();
+        }
+    }
+
+    // Must be called exactly once per item returned by pop(), after its
+    // subdirectories (if any) have already been pushed.
+    fn finish(&self) {
+        if self.outstanding.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.cond.notify_all();
+        }
+    }
+}
+
+fn process_work_item(
+    root: &Path,
+    item: &WorkItem,
+    opts: &Opts,
+    queue: &WorkQueue,
+    writer: &Mutex<&mut (dyn RecordWriter + Send)>,
+) {
+    if item.depth > opts.max_depth {
+        return;
+    }
+
+    let extra_ignore_file = if item.depth == 0 {
+        opts.ignore_file.as_deref()
+    } else {
+        None
+    };
+    let ignore = if opts.no_ignore {
+        item.ignore.clone()
+    } else {
+        item.ignore.extended(&item.dir, extra_ignore_file)
+    };
+
+    let entries = match fs::read_dir(&item.dir) {
+        Ok(v) => v,
+        Err(_) => return,
     };
 
     for entry_res in entries {
@@ -112,32 +1224,104 @@ fn scan_dir(
 
         if ft.is_dir() {
             let dir_name = entry.file_name().to_string_lossy().to_string();
-            if should_exclude_dir(&dir_name, &rel_posix, &opts.exclude_dirs) {
+            if opts.exclude_dirs.is_match(&dir_name, &rel_posix) {
                 continue;
             }
+            if !opts.no_ignore && ignore.is_ignored(&path, true) {
+                continue;
+            }
+            if opts.types.dirs && should_emit(opts, &ft, false, &path) {
+                if let Ok(meta) = fs::metadata(&path) {
+                    let mtime = mtime_of(&meta);
+                    let size = meta.len();
+                    if passes_filters(opts, mtime, size) {
+                        let mut w = writer.lock().unwrap();
+                        let _ = w.write_record(&path, mtime, size);
+                    }
+                }
+            }
             if !opts.follow_symlinks && ft.is_symlink() {
                 continue;
             }
-            let _ = scan_dir(root, &path, depth + 1, opts, out);
+            queue.push(WorkItem {
+                dir: path,
+                depth: item.depth + 1,
+                ignore: ignore.clone(),
+            });
             continue;
         }
 
         if ft.is_file() {
+            if !opts.no_ignore && ignore.is_ignored(&path, false) {
+                continue;
+            }
             let meta = match fs::metadata(&path) {
                 Ok(m) => m,
                 Err(_) => continue,
             };
-            let mtime = meta
-                .modified()
-                .ok()
-                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                .map(|d| d.as_secs() as i64)
-                .unwrap_or(0);
+            let mtime = mtime_of(&meta);
             let size = meta.len();
-            // Keep raw entry path to avoid expensive per-file canonicalize.
-            writeln!(out, "{}\t{}\t{}", path.display(), mtime, size)?;
+            if !passes_filters(opts, mtime, size) {
+                continue;
+            }
+            if should_emit(opts, &ft, is_executable(&meta), &path) {
+                let mut w = writer.lock().unwrap();
+                let _ = w.write_record(&path, mtime, size);
+            }
+            continue;
+        }
+
+        if ft.is_symlink() {
+            if !opts.no_ignore && ignore.is_ignored(&path, false) {
+                continue;
+            }
+            if opts.types.symlinks && should_emit(opts, &ft, false, &path) {
+                if let Ok(meta) = fs::symlink_metadata(&path) {
+                    let mtime = mtime_of(&meta);
+                    let size = meta.len();
+                    if passes_filters(opts, mtime, size) {
+                        let mut w = writer.lock().unwrap();
+                        let _ = w.write_record(&path, mtime, size);
+                    }
+                }
+            }
         }
     }
+}
+
+// Work-stealing counterpart to scan_dir: opts.threads workers share one
+// queue of directories, each popping one, emitting records through a
+// single mutex-guarded writer, and pushing subdirectories back for
+// whichever worker gets to them next.
+fn parallel_scan_dir(
+    root: &Path,
+    opts: &Opts,
+    out: &mut (dyn RecordWriter + Send),
+) -> io::Result<()> {
+    let queue = WorkQueue {
+        items: Mutex::new(VecDeque::new()),
+        cond: Condvar::new(),
+        outstanding: AtomicUsize::new(0),
+    };
+    queue.push(WorkItem {
+        dir: root.to_path_buf(),
+        depth: 0,
+        ignore: IgnoreChain::new(),
+    });
+
+    let writer = Mutex::new(out);
+    std::thread::scope(|scope| {
+        for _ in 0..opts.threads {
+            let queue = &queue;
+            let writer = &writer;
+            scope.spawn(move || {
+                while let Some(item) = queue.pop() {
+                    process_work_item(root, &item, opts, queue, writer);
+                    queue.finish();
+                }
+            });
+        }
+    });
     Ok(())
 }
 
@@ -150,9 +1334,358 @@ fn main() {
         }
     };
 
-    let mut out = io::BufWriter::new(io::stdout());
-    if let Err(e) = scan_dir(&opts.root, &opts.root, 0, &opts, &mut out) {
+    let stdout = io::BufWriter::new(io::stdout());
+    let mut writer = match build_writer(opts.format, stdout) {
+        Ok(w) => w,
+        Err(e) => {
+            eprintln!("scan failed: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let result = if opts.threads > 1 {
+        parallel_scan_dir(&opts.root, &opts, writer.as_mut())
+    } else {
+        let mut ignore = IgnoreStack::new();
+        scan_dir(&opts.root, &opts.root, 0, &opts, &mut ignore, writer.as_mut())
+    };
+    if let Err(e) = result {
         eprintln!("scan failed: {}", e);
         std::process::exit(1);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glob_segment_match_handles_wildcards_and_classes() {
+        assert!(glob_segment_match("*.min.js", "app.min.js"));
+        assert!(!glob_segment_match("*.min.js", "app.js"));
+        assert!(glob_segment_match("a?c", "abc"));
+        assert!(glob_segment_match("[a-c]og", "bog"));
+        assert!(!glob_segment_match("[!a-c]og", "bog"));
+    }
+
+    #[test]
+    fn glob_segment_match_resolves_many_stars_without_blowing_up() {
+        // Used to backtrack exponentially; this just needs to return promptly.
+        let pattern = "*a".repeat(30) + "x";
+        let text = "a".repeat(60) + "y";
+        assert!(!glob_segment_match(&pattern, &text));
+    }
+
+    #[test]
+    fn glob_match_segments_supports_double_star() {
+        let pattern: Vec<String> = vec!["**".to_string(), "target".to_string()];
+        assert!(glob_match_segments(&pattern, &["a", "b", "target"]));
+        assert!(!glob_match_segments(&pattern, &["a", "b", "other"]));
+    }
+
+    fn rule(pattern: &str) -> IgnoreRule {
+        IgnoreRule::parse(pattern).expect("valid pattern")
+    }
+
+    #[test]
+    fn negated_rule_overrides_an_earlier_match_in_the_same_frame() {
+        let frame = IgnoreFrame {
+            base: PathBuf::from("/root"),
+            rules: vec![rule("*.log"), rule("!keep.log")],
+        };
+        let frames = [&frame];
+        assert!(is_ignored_by_frames(frames.into_iter(), Path::new("/root/app.log"), false));
+        assert!(!is_ignored_by_frames(frames.into_iter(), Path::new("/root/keep.log"), false));
+    }
+
+    #[test]
+    fn a_deeper_frame_overrides_a_shallower_one() {
+        let outer = IgnoreFrame {
+            base: PathBuf::from("/root"),
+            rules: vec![rule("*.log")],
+        };
+        let inner = IgnoreFrame {
+            base: PathBuf::from("/root/build"),
+            rules: vec![rule("!important.log")],
+        };
+        let frames = [&outer, &inner];
+        assert!(is_ignored_by_frames(frames.into_iter(), Path::new("/root/build/debug.log"), false));
+        assert!(!is_ignored_by_frames(frames.into_iter(), Path::new("/root/build/important.log"), false));
+    }
+
+    #[test]
+    fn work_queue_terminates_once_drained() {
+        let queue = WorkQueue {
+            items: Mutex::new(VecDeque::new()),
+            cond: Condvar::new(),
+            outstanding: AtomicUsize::new(0),
+        };
+        queue.push(WorkItem {
+            dir: PathBuf::from("/tmp"),
+            depth: 0,
+            ignore: IgnoreChain::new(),
+        });
+        let item = queue.pop().expect("one item was pushed");
+        assert_eq!(item.depth, 0);
+        queue.finish();
+        assert!(queue.pop().is_none());
+    }
+
+    struct VecWriter {
+        records: Vec<PathBuf>,
+    }
+
+    impl RecordWriter for VecWriter {
+        fn write_record(&mut self, path: &Path, _mtime: i64, _size: u64) -> io::Result<()> {
+            self.records.push(path.to_path_buf());
+            Ok(())
+        }
+    }
+
+    fn test_opts(root: &Path, threads: usize) -> Opts {
+        Opts {
+            root: root.to_path_buf(),
+            max_depth: 64,
+            follow_symlinks: false,
+            exclude_dirs: Arc::new(GlobSet::build(&[])),
+            ignore_file: None,
+            no_ignore: true,
+            threads,
+            min_size: None,
+            max_size: None,
+            newer_than: None,
+            older_than: None,
+            format: Format::Tsv,
+            types: EntryTypes::default(),
+            extensions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn parallel_walk_agrees_with_sequential_walk() {
+        let dir = env::temp_dir().join(format!("sari_test_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("a/b")).unwrap();
+        fs::write(dir.join("a/f1.txt"), "hi").unwrap();
+        fs::write(dir.join("a/b/f2.txt"), "hi").unwrap();
+        fs::write(dir.join("top.txt"), "hi").unwrap();
+
+        let mut seq = VecWriter { records: Vec::new() };
+        let mut ignore = IgnoreStack::new();
+        scan_dir(&dir, &dir, 0, &test_opts(&dir, 1), &mut ignore, &mut seq).unwrap();
+
+        let mut par = VecWriter { records: Vec::new() };
+        parallel_scan_dir(&dir, &test_opts(&dir, 4), &mut par).unwrap();
+
+        let mut seq_paths = seq.records;
+        let mut par_paths = par.records;
+        seq_paths.sort();
+        par_paths.sort();
+        assert_eq!(seq_paths, par_paths);
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn parse_rfc3339_handles_leap_years() {
+        assert_eq!(parse_rfc3339("2024-02-29T00:00:00Z"), Some(1709164800));
+        assert_eq!(parse_rfc3339("2000-02-29T00:00:00Z"), Some(951782400));
+        // 1900 is divisible by 100 but not 400, so not a leap year: no Feb 29.
+        assert_eq!(parse_rfc3339("1900-02-29T00:00:00Z"), None);
+    }
+
+    #[test]
+    fn parse_rfc3339_handles_pre_1970_dates() {
+        assert_eq!(parse_rfc3339("1969-12-31T00:00:00Z"), Some(-86400));
+        assert_eq!(parse_rfc3339("1900-01-01T00:00:00Z"), Some(-2208988800));
+    }
+
+    #[test]
+    fn parse_rfc3339_applies_utc_offset() {
+        assert_eq!(
+            parse_rfc3339("2024-01-01T05:00:00+05:00"),
+            Some(1704067200)
+        );
+        assert_eq!(
+            parse_rfc3339("2024-01-01T05:00:00+05:00"),
+            parse_rfc3339("2024-01-01T00:00:00Z")
+        );
+    }
+
+    #[test]
+    fn parse_rfc3339_rejects_garbage() {
+        assert_eq!(parse_rfc3339("not a date"), None);
+        assert_eq!(parse_rfc3339("2024-13-01T00:00:00Z"), None);
+    }
+
+    #[test]
+    fn json_escape_path_escapes_special_characters() {
+        assert_eq!(json_escape_path(Path::new("plain.txt")), "\"plain.txt\"");
+        assert_eq!(
+            json_escape_path(Path::new("a\"b\\c\nd")),
+            "\"a\\\"b\\\\c\\nd\""
+        );
+    }
+
+    fn read_varint(buf: &[u8], pos: &mut usize) -> u64 {
+        let mut value = 0u64;
+        let mut shift = 0;
+        loop {
+            let byte = buf[*pos];
+            *pos += 1;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return value;
+            }
+            shift += 7;
+        }
+    }
+
+    #[test]
+    fn bin_writer_round_trips_header_and_records() {
+        let mut buf = Vec::new();
+        {
+            let mut w = BinWriter::new(&mut buf).unwrap();
+            // Name long enough that its varint length needs a continuation byte.
+            let long_name = "a".repeat(200);
+            w.write_record(Path::new(&long_name), 1234567890, 42).unwrap();
+            w.write_record(Path::new("c"), -5, 300).unwrap();
+
+            assert_eq!(&buf[0..4], BIN_MAGIC);
+            assert_eq!(buf[4], BIN_VERSION);
+
+            let mut pos = 5;
+            let len1 = read_varint(&buf, &mut pos) as usize;
+            assert_eq!(len1, 200);
+            let path1 = std::str::from_utf8(&buf[pos..pos + len1]).unwrap();
+            pos += len1;
+            let mtime1 = i64::from_le_bytes(buf[pos..pos + 8].try_into().unwrap());
+            pos += 8;
+            let size1 = u64::from_le_bytes(buf[pos..pos + 8].try_into().unwrap());
+            pos += 8;
+            assert_eq!(path1, long_name);
+            assert_eq!(mtime1, 1234567890);
+            assert_eq!(size1, 42);
+
+            let len2 = read_varint(&buf, &mut pos) as usize;
+            let path2 = std::str::from_utf8(&buf[pos..pos + len2]).unwrap();
+            pos += len2;
+            let mtime2 = i64::from_le_bytes(buf[pos..pos + 8].try_into().unwrap());
+            pos += 8;
+            let size2 = u64::from_le_bytes(buf[pos..pos + 8].try_into().unwrap());
+            pos += 8;
+            assert_eq!(path2, "c");
+            assert_eq!(mtime2, -5);
+            assert_eq!(size2, 300);
+
+            assert_eq!(pos, buf.len());
+        }
+    }
+
+    #[test]
+    fn extension_matches_is_case_sensitive_and_exact() {
+        assert!(extension_matches(Path::new("a.txt"), &[]));
+        assert!(extension_matches(
+            Path::new("a.txt"),
+            &["txt".to_string(), "rs".to_string()]
+        ));
+        assert!(!extension_matches(Path::new("a.txt"), &["rs".to_string()]));
+        assert!(!extension_matches(Path::new("a.TXT"), &["txt".to_string()]));
+        assert!(!extension_matches(Path::new("noext"), &["txt".to_string()]));
+    }
+
+    #[test]
+    fn should_emit_respects_type_and_extension_filters() {
+        let dir = env::temp_dir().join(format!("sari_should_emit_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("script.sh");
+        fs::write(&file_path, "hi").unwrap();
+        let dir_path = dir.join("subdir");
+        fs::create_dir(&dir_path).unwrap();
+
+        let file_ft = fs::symlink_metadata(&file_path).unwrap().file_type();
+        let dir_ft = fs::symlink_metadata(&dir_path).unwrap().file_type();
+
+        // Default --type (files only): a file passes, a directory doesn't.
+        let default_opts = test_opts(&dir, 1);
+        assert!(should_emit(&default_opts, &file_ft, false, &file_path));
+        assert!(!should_emit(&default_opts, &dir_ft, false, &dir_path));
+
+        // --type d: only directories pass.
+        let dirs_only = Opts {
+            types: EntryTypes {
+                files: false,
+                dirs: true,
+                symlinks: false,
+                executables: false,
+            },
+            ..test_opts(&dir, 1)
+        };
+        assert!(!should_emit(&dirs_only, &file_ft, false, &file_path));
+        assert!(should_emit(&dirs_only, &dir_ft, false, &dir_path));
+
+        // --type x --extension sh: executable files pass only with a matching extension.
+        let exec_sh = Opts {
+            types: EntryTypes {
+                files: false,
+                dirs: false,
+                symlinks: false,
+                executables: true,
+            },
+            extensions: vec!["sh".to_string()],
+            ..test_opts(&dir, 1)
+        };
+        assert!(should_emit(&exec_sh, &file_ft, true, &file_path));
+        assert!(!should_emit(&exec_sh, &file_ft, false, &file_path));
+        let txt_path = dir.join("script.txt");
+        assert!(!should_emit(&exec_sh, &file_ft, true, &txt_path));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn is_executable_checks_the_unix_mode_bits() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = env::temp_dir().join(format!("sari_is_exec_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+
+        let plain = dir.join("plain.txt");
+        fs::write(&plain, "hi").unwrap();
+        assert!(!is_executable(&fs::metadata(&plain).unwrap()));
+
+        let exec = dir.join("run.sh");
+        fs::write(&exec, "hi").unwrap();
+        let mut perms = fs::metadata(&exec).unwrap().permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(&exec, perms).unwrap();
+        assert!(is_executable(&fs::metadata(&exec).unwrap()));
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[cfg(not(unix))]
+    #[test]
+    fn is_executable_is_always_false_off_unix() {
+        let dir = env::temp_dir().join(format!("sari_is_exec_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("anything");
+        fs::write(&path, "hi").unwrap();
+        assert!(!is_executable(&fs::metadata(&path).unwrap()));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn parse_size_handles_suffixes_and_edge_cases() {
+        assert_eq!(parse_size("0k"), Ok(0));
+        assert_eq!(parse_size("10k"), Ok(10 * 1024));
+        assert_eq!(parse_size("5M"), Ok(5 * 1024 * 1024));
+        assert_eq!(parse_size("1G"), Ok(1024 * 1024 * 1024));
+        assert_eq!(parse_size("42"), Ok(42));
+        assert!(parse_size("").is_err());
+        assert!(parse_size(&format!("{}g", u64::MAX)).is_err());
+    }
+}